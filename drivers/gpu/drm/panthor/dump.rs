@@ -15,11 +15,12 @@
 //!
 
 use core::mem;
-use core::ptr::NonNull;
 
-use alloc::DumpAllocator;
 use kernel::bindings;
+use kernel::drm::gem::RawGemObject;
 use kernel::prelude::*;
+use kernel::types::AsBytes;
+use kernel::vmalloc::VmallocBuffer;
 
 use crate::regs;
 use crate::regs::GpuRegister;
@@ -39,8 +40,23 @@ enum HeaderType {
     Vm,
     /// A dump of the firmware interface
     FirmwareInterface,
+    /// Dump metadata: format version and device identity (see [`Metadata`]).
+    Metadata,
+    /// A table mapping register offsets to their symbolic names (see [`RegisterName`]).
+    RegisterNames,
+    /// The trailing section index (see [`SectionIndexEntry`]).
+    SectionIndex,
 }
 
+/// Rounds `n` up to the next multiple of 8, matching the alignment
+/// [`VmallocBuffer`](kernel::vmalloc::VmallocBuffer) applies to every appended section.
+const fn align8(n: usize) -> usize {
+    (n + 7) & !7
+}
+
+/// The number of bytes reserved for a register's symbolic name in the name table.
+const REGISTER_NAME_LEN: usize = 24;
+
 #[repr(C)]
 pub(crate) struct DumpArgs {
     dev: *mut bindings::device,
@@ -52,14 +68,90 @@ pub(crate) struct DumpArgs {
     bo_count: usize,
     /// The base address of the registers to use when reading.
     reg_base_addr: *mut core::ffi::c_void,
+    /// The PCI vendor ID of the GPU, recorded in the dump metadata.
+    pci_vendor_id: u16,
+    /// The PCI device ID of the GPU, recorded in the dump metadata.
+    pci_device_id: u16,
+}
+
+/// The metadata section written at the start of every dump.
+///
+/// It makes the dump self-describing: a decoder reads `major`/`minor` to learn the format version
+/// and the device identity without having to parse any later section.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub(crate) struct Metadata {
+    major: u32,
+    minor: u32,
+    pci_vendor_id: u16,
+    pci_device_id: u16,
+    slot: i32,
+}
+
+// SAFETY: `Metadata` is a `repr(C)` aggregate of POD fields.
+unsafe impl AsBytes for Metadata {}
+
+/// One entry of the register name table: a register offset and its symbolic name, NUL-padded to a
+/// fixed width so the table is a flat array a decoder can index.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub(crate) struct RegisterName {
+    offset: u64,
+    name: [u8; REGISTER_NAME_LEN],
+}
+
+// SAFETY: `RegisterName` is a `repr(C)` aggregate of POD fields.
+unsafe impl AsBytes for RegisterName {}
+
+impl RegisterName {
+    fn new(reg: GpuRegister, name: &str) -> Self {
+        let mut buf = [0u8; REGISTER_NAME_LEN];
+        let src = name.as_bytes();
+        let len = core::cmp::min(src.len(), REGISTER_NAME_LEN - 1);
+        buf[..len].copy_from_slice(&src[..len]);
+        Self {
+            offset: reg.offset(),
+            name: buf,
+        }
+    }
+}
+
+/// One entry of the trailing section index, letting a decoder seek a section by type without
+/// assuming the order in which the producer emitted them.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub(crate) struct SectionIndexEntry {
+    ty: u32,
+    padding: u32,
+    offset: u64,
+    size: u64,
 }
 
+// SAFETY: `SectionIndexEntry` is a `repr(C)` aggregate of POD fields.
+unsafe impl AsBytes for SectionIndexEntry {}
+
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub(crate) struct Header {
     magic: u32,
     ty: HeaderType,
     size: u32,
-    padding: u16,
+    padding: u32,
+}
+
+// SAFETY: `Header` is a `repr(C)` aggregate of integer fields with no pointers or interior
+// mutability, so its bytes are safe to expose.
+unsafe impl AsBytes for Header {}
+
+impl Header {
+    fn new(ty: HeaderType, size: u32) -> Self {
+        Self {
+            magic: MAGIC,
+            ty,
+            size,
+            padding: 0,
+        }
+    }
 }
 
 #[repr(C)]
@@ -67,168 +159,148 @@ pub(crate) struct Header {
 pub(crate) struct RegisterDump {
     register: GpuRegister,
     value: u32,
+    padding: u32,
 }
 
-/// The registers to dump
-const REGISTERS: [GpuRegister; 18] = [
-    regs::SHADER_READY_LO,
-    regs::SHADER_READY_HI,
-    regs::TILER_READY_LO,
-    regs::TILER_READY_HI,
-    regs::L2_READY_LO,
-    regs::L2_READY_HI,
-    regs::JOB_INT_MASK,
-    regs::JOB_INT_STAT,
-    regs::MMU_INT_MASK,
-    regs::MMU_INT_STAT,
-    regs::as_transtab_lo(0),
-    regs::as_transtab_hi(0),
-    regs::as_memattr_lo(0),
-    regs::as_memattr_hi(0),
-    regs::as_faultstatus(0),
-    regs::as_faultaddress_lo(0),
-    regs::as_faultaddress_hi(0),
-    regs::as_status(0),
+// SAFETY: `RegisterDump` is a `repr(C)` aggregate of POD fields with no pointers or interior
+// mutability, so its bytes are safe to expose.
+unsafe impl AsBytes for RegisterDump {}
+
+/// The registers to dump, paired with the symbolic name emitted into the register name table so a
+/// decoder need not hardcode the offsets.
+const REGISTERS: [(GpuRegister, &str); 18] = [
+    (regs::SHADER_READY_LO, "SHADER_READY_LO"),
+    (regs::SHADER_READY_HI, "SHADER_READY_HI"),
+    (regs::TILER_READY_LO, "TILER_READY_LO"),
+    (regs::TILER_READY_HI, "TILER_READY_HI"),
+    (regs::L2_READY_LO, "L2_READY_LO"),
+    (regs::L2_READY_HI, "L2_READY_HI"),
+    (regs::JOB_INT_MASK, "JOB_INT_MASK"),
+    (regs::JOB_INT_STAT, "JOB_INT_STAT"),
+    (regs::MMU_INT_MASK, "MMU_INT_MASK"),
+    (regs::MMU_INT_STAT, "MMU_INT_STAT"),
+    (regs::as_transtab_lo(0), "AS0_TRANSTAB_LO"),
+    (regs::as_transtab_hi(0), "AS0_TRANSTAB_HI"),
+    (regs::as_memattr_lo(0), "AS0_MEMATTR_LO"),
+    (regs::as_memattr_hi(0), "AS0_MEMATTR_HI"),
+    (regs::as_faultstatus(0), "AS0_FAULTSTATUS"),
+    (regs::as_faultaddress_lo(0), "AS0_FAULTADDRESS_LO"),
+    (regs::as_faultaddress_hi(0), "AS0_FAULTADDRESS_HI"),
+    (regs::as_status(0), "AS0_STATUS"),
 ];
 
-mod alloc {
-    use core::ptr::NonNull;
-
-    use kernel::bindings;
-    use kernel::prelude::*;
-
-    use crate::dump::Header;
-    use crate::dump::HeaderType;
-    use crate::dump::MAGIC;
+/// Accumulates dump sections into a [`VmallocBuffer`] while recording a section index, so the
+/// emitted dump is self-describing and seekable by section type.
+struct Dumper {
+    buf: VmallocBuffer,
+    index: Vec<SectionIndexEntry>,
+}
 
-    pub(crate) struct DumpAllocator {
-        mem: NonNull<core::ffi::c_void>,
-        pos: usize,
-        capacity: usize,
+impl Dumper {
+    fn new(buf: VmallocBuffer, capacity: usize) -> Result<Self> {
+        Ok(Self {
+            buf,
+            index: Vec::with_capacity(capacity, GFP_KERNEL)?,
+        })
     }
 
-    impl DumpAllocator {
-        pub(crate) fn new(size: usize) -> Result<Self> {
-            if isize::try_from(size).unwrap() == isize::MAX {
-                return Err(EINVAL);
-            }
-
-            // Let's cheat a bit here, since there is no Rust vmalloc allocator
-            // for the time being.
-            //
-            // Safety: just a FFI call to alloc memory
-            let mem = NonNull::new(unsafe {
-                bindings::__vmalloc_noprof(
-                    size.try_into().unwrap(),
-                    bindings::GFP_KERNEL | bindings::GFP_NOWAIT | 1 << bindings::___GFP_NORETRY_BIT,
-                )
-            });
-
-            let mem = match mem {
-                Some(buffer) => buffer,
-                None => return Err(ENOMEM),
-            };
-
-            // Ssfety: just a FFI call to zero out the memory
-            unsafe { core::ptr::write_bytes(mem.as_ptr(), 0, size) };
-            Ok(Self {
-                mem,
-                pos: 0,
-                capacity: size,
-            })
-        }
-
-        fn alloc_mem(&mut self, size: usize) -> Option<*mut u8> {
-            assert!(size % 8 == 0, "Allocation size must be 8-byte aligned");
-            if isize::try_from(size).unwrap() == isize::MAX {
-                return None;
-            } else if self.pos + size > self.capacity {
-                kernel::pr_debug!("DumpAllocator out of memory");
-                None
-            } else {
-                let offset = self.pos;
-                self.pos += size;
-
-                // Safety: we know that this is a valid allocation, so
-                // dereferencing is safe. We don't ever return two pointers to
-                // the same address, so we adhere to the aliasing rules. We make
-                // sure that the memory is zero-initialized before being handed
-                // out (this happens when the allocator is first created) and we
-                // enforce a 8 byte alignment rule.
-                Some(unsafe { self.mem.as_ptr().offset(offset as isize) as *mut u8 })
-            }
-        }
-
-        pub(crate) fn alloc<T>(&mut self) -> Option<&mut T> {
-            let mem = self.alloc_mem(core::mem::size_of::<T>())? as *mut T;
-            // Safety: we uphold safety guarantees in alloc_mem(), so this is
-            // safe to dereference.
-            Some(unsafe { &mut *mem })
-        }
-
-        pub(crate) fn alloc_bytes(&mut self, num_bytes: usize) -> Option<&mut [u8]> {
-            let mem = self.alloc_mem(num_bytes)?;
-
-            // Safety: we uphold safety guarantees in alloc_mem(), so this is
-            // safe to build a slice
-            Some(unsafe { core::slice::from_raw_parts_mut(mem, num_bytes) })
-        }
+    /// Records a `[start, self.buf.len())` byte range as a section of type `ty` in the index.
+    fn record(&mut self, ty: HeaderType, start: usize) -> Result {
+        self.index.push(
+            SectionIndexEntry {
+                ty: ty as u32,
+                padding: 0,
+                offset: start as u64,
+                size: (self.buf.len() - start) as u64,
+            },
+            GFP_KERNEL,
+        )
+    }
+}
 
-        pub(crate) fn alloc_header(&mut self, ty: HeaderType, size: u32) -> &mut Header {
-            let hdr: &mut Header = self.alloc().unwrap();
-            hdr.magic = MAGIC;
-            hdr.ty = ty;
-            hdr.size = size;
-            hdr
-        }
+fn dump_metadata(d: &mut Dumper, args: &DumpArgs) -> Result {
+    let start = d.buf.len();
+    d.buf
+        .append(&Header::new(HeaderType::Metadata, mem::size_of::<Metadata>() as u32))?;
+    d.buf.append(&Metadata {
+        major: MAJOR,
+        minor: MINOR,
+        pci_vendor_id: args.pci_vendor_id,
+        pci_device_id: args.pci_device_id,
+        slot: args.slot,
+    })?;
+    d.record(HeaderType::Metadata, start)
+}
 
-        pub(crate) fn is_end(&self) -> bool {
-            self.pos == self.capacity
-        }
+fn dump_register_names(d: &mut Dumper) -> Result {
+    let start = d.buf.len();
+    let sz = REGISTERS.len() * mem::size_of::<RegisterName>();
+    d.buf
+        .append(&Header::new(HeaderType::RegisterNames, sz.try_into()?))?;
 
-        pub(crate) fn dump(self) -> (NonNull<core::ffi::c_void>, usize) {
-            (self.mem, self.capacity)
-        }
+    for (reg, name) in &REGISTERS {
+        d.buf.append(&RegisterName::new(*reg, name))?;
     }
+
+    d.record(HeaderType::RegisterNames, start)
 }
 
-fn dump_registers(alloc: &mut DumpAllocator, args: &DumpArgs) {
-    let sz = core::mem::size_of_val(&REGISTERS);
-    let header: &mut Header = alloc.alloc_header(HeaderType::Registers, sz.try_into().unwrap());
+fn dump_registers(d: &mut Dumper, args: &DumpArgs) -> Result {
+    let start = d.buf.len();
+    let sz = REGISTERS.len() * mem::size_of::<RegisterDump>();
+    d.buf
+        .append(&Header::new(HeaderType::Registers, sz.try_into()?))?;
 
     // TODO: js_as_offset;
-    for reg in &REGISTERS {
-        let dumped_reg: &mut RegisterDump = alloc.alloc().unwrap();
-        dumped_reg.register = *reg;
-        dumped_reg.value = reg.read(args.reg_base_addr);
+    for (reg, _) in &REGISTERS {
+        d.buf.append(&RegisterDump {
+            register: *reg,
+            value: reg.read(args.reg_base_addr),
+            padding: 0,
+        })?;
     }
+
+    d.record(HeaderType::Registers, start)
 }
 
-fn dump_bo(alloc: &mut DumpAllocator, bo: &mut bindings::drm_gem_object) {
-    let mut map = bindings::iosys_map::default();
+fn dump_bo(d: &mut Dumper, bo: &RawGemObject) -> Result {
+    let map = match bo.vmap() {
+        Ok(map) => map,
+        Err(e) => {
+            pr_warn!("Failed to map BO");
+            return Err(e);
+        }
+    };
 
-    // Safety: we trust the kernel to provide a valid BO.
-    let ret = unsafe { bindings::drm_gem_vmap_unlocked(bo, &mut map as _) };
-    if ret != 0 {
-        pr_warn!("Failed to map BO");
-        return;
-    }
+    let mapped_bo = map.as_slice();
+    let sz = mapped_bo.len();
+
+    // `VmallocBuffer::append_bytes` pads each section to an 8-byte boundary for us; record how
+    // much padding a decoder should skip.
+    let padding = (8 - sz % 8) % 8;
+    let mut header = Header::new(HeaderType::Vm, sz as u32);
+    header.padding = padding as u32;
 
-    let sz = bo.size;
+    let start = d.buf.len();
+    d.buf
+        .append(&header)
+        .and_then(|()| d.buf.append_bytes(mapped_bo))?;
 
-    // Safety: we know that the vaddr is valid and we know the BO size.
-    let mapped_bo: &mut [u8] =
-        unsafe { core::slice::from_raw_parts_mut(map.__bindgen_anon_1.vaddr as *mut _, sz) };
+    d.record(HeaderType::Vm, start)
+}
 
-    let padding = (8 - bo.size % 8) % 8;
-    let header = alloc.alloc_header(HeaderType::Vm, sz as u32);
-    header.padding = padding as u16;
+/// Writes the trailing section index, which is itself not recorded as a section.
+fn dump_section_index(d: &mut Dumper) -> Result {
+    let sz = d.index.len() * mem::size_of::<SectionIndexEntry>();
+    d.buf
+        .append(&Header::new(HeaderType::SectionIndex, sz.try_into()?))?;
 
-    let bo_data = alloc.alloc_bytes(sz + padding).unwrap();
-    bo_data.copy_from_slice(&mapped_bo[..]);
+    for i in 0..d.index.len() {
+        let entry = d.index[i];
+        d.buf.append(&entry)?;
+    }
 
-    // Safety: BO is valid and was previously mapped.
-    unsafe { bindings::drm_gem_vunmap_unlocked(bo, &mut map as _) };
+    Ok(())
 }
 
 /// Dumps the current state of the GPU to a file
@@ -243,63 +315,69 @@ pub(crate) extern "C" fn panthor_core_dump(args: *const DumpArgs) -> core::ffi::
     // Safety: we checked whether the pointer was null. It is assumed to be
     // aligned as per the safety requirements.
     let args = unsafe { &*args };
-    // Safety: `args` is assumed valid as per the safety requirements.
-    //
-    // TODO: Ideally, we would use the safe GEM abstraction from the kernel
-    // crate, but I see no way to create a drm::gem::ObjectRef from a
-    // bindings::drm_gem_object. drm::gem::IntoGEMObject is only implemented for
-    // drm::gem::Object, which means that new references can only be created
-    // from a Rust-owned GEM object.
-    //
-    // It also has a has a `type Driver: drv::Driver` associated type, from
-    // which it can access the `File` associated type. But not all GEM functions
-    // take a file, though. For example, `drm_gem_vmap_unlocked` (used here)
-    // does not.
-    //
-    // This associated type is a blocker here, because there is no actual
-    // drv::Driver. We're only implementing a few functions in Rust.
+    // Safety: `args` is assumed valid as per the safety requirements. We wrap each bare
+    // `drm_gem_object` in a `RawGemObject`, which gives us size and mapping without needing the
+    // driver-specific GEM subtype or a `drv::Driver` (this driver is still C).
     let mut bos = match Vec::with_capacity(args.bo_count, GFP_KERNEL) {
         Ok(bos) => bos,
         Err(_) => return ENOMEM.to_errno(),
     };
     for i in 0..args.bo_count {
         // Safety: `args` is assumed valid as per the safety requirements.
-        // `bos` is a valid pointer to a valid array of valid pointers.
-        let bo = unsafe { &mut **args.bos.add(i) };
+        // `bos` is a valid pointer to a valid array of valid pointers, each of which is a valid
+        // `drm_gem_object` held alive by the caller for the duration of this call.
+        let bo = unsafe { RawGemObject::from_raw(*args.bos.add(i)) };
         bos.push(bo, GFP_KERNEL).unwrap();
     }
 
-    // let mut bos: Vec<&mut bindings::drm_gem_object> = (0..args.bo_count)
-    // .map(|i| unsafe { &mut **args.bos.add(i) })
-    // .collect();
-
-    let mut file_size = core::mem::size_of::<Header>();
-    file_size += REGISTERS.len() * core::mem::size_of::<RegisterDump>();
+    let hdr = align8(core::mem::size_of::<Header>());
+    // metadata + register-name table + register values.
+    let mut file_size = hdr + align8(core::mem::size_of::<Metadata>());
+    file_size += hdr + REGISTERS.len() * align8(core::mem::size_of::<RegisterName>());
+    file_size += hdr + REGISTERS.len() * align8(core::mem::size_of::<RegisterDump>());
 
-    for bo in &mut *bos {
-        file_size += core::mem::size_of::<Header>();
-        file_size += bo.size;
-        let padding = ((8 - bo.size % 8) % 8) as u16;
-        file_size += padding as usize;
+    for bo in &bos {
+        file_size += hdr + align8(bo.size());
     }
 
+    // The trailing section index has one entry per section above.
+    let num_sections = 3 + bos.len();
+    file_size += hdr + num_sections * align8(core::mem::size_of::<SectionIndexEntry>());
+
     // Everything must fit within this allocation, otherwise it was miscomputed.
-    let mut alloc = match DumpAllocator::new(file_size) {
-        Ok(alloc) => alloc,
+    let buf = match VmallocBuffer::new(file_size) {
+        Ok(buf) => buf,
+        Err(e) => return e.to_errno(),
+    };
+    let mut d = match Dumper::new(buf, num_sections) {
+        Ok(d) => d,
         Err(e) => return e.to_errno(),
     };
 
-    dump_registers(&mut alloc, &args);
-    for bo in bos {
-        dump_bo(&mut alloc, bo);
+    if let Err(e) = dump_metadata(&mut d, args)
+        .and_then(|()| dump_register_names(&mut d))
+        .and_then(|()| dump_registers(&mut d, args))
+    {
+        return e.to_errno();
+    }
+    for bo in &bos {
+        // A failure to map or append a single BO should not abort the whole dump; skip it.
+        let _ = dump_bo(&mut d, bo);
+    }
+    if let Err(e) = dump_section_index(&mut d) {
+        return e.to_errno();
     }
 
-    if alloc.is_end() {
-        pr_warn!("DumpAllocator: wrong allocation size");
+    // A skipped (unmappable) BO leaves the buffer shorter than the space reserved for it, so the
+    // normal partial-dump path under-fills the allocation. Only an *overrun* past the reserved
+    // capacity signals a miscomputed size.
+    if d.buf.len() > d.buf.capacity() {
+        pr_warn!("VmallocBuffer: wrong allocation size");
     }
 
-    let (mem, size) = alloc.dump();
-    // Safety: `mem` is a valid pointer to a valid allocation of `size` bytes.
-    unsafe { bindings::dev_coredumpv(args.dev, mem.as_ptr(), size, bindings::GFP_KERNEL) };
+    let (mem, size) = d.buf.into_raw();
+    // Safety: `mem` is a valid pointer to a valid allocation of `size` bytes. `dev_coredumpv`
+    // takes ownership of the allocation and frees it with `vfree`.
+    unsafe { bindings::dev_coredumpv(args.dev, mem.as_ptr() as _, size, bindings::GFP_KERNEL) };
     0
 }
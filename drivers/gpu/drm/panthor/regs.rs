@@ -7,6 +7,10 @@
 #![allow(unused_macros, unused_imports, dead_code)]
 
 use kernel::bindings;
+use kernel::iomem::IoMem;
+use kernel::prelude::*;
+use kernel::types::AsBytes;
+use kernel::vmalloc::VmallocBuffer;
 
 use core::ops::Add;
 use core::ops::Shl;
@@ -17,6 +21,11 @@ use core::ops::Shr;
 pub(crate) struct GpuRegister(u64);
 
 impl GpuRegister {
+    /// Returns the register's offset from the MMIO base.
+    pub(crate) fn offset(&self) -> u64 {
+        self.0
+    }
+
     pub(crate) fn read(&self, iomem: *const core::ffi::c_void) -> u32 {
         // Safety: `reg` represents a valid address
         unsafe {
@@ -24,6 +33,32 @@ impl GpuRegister {
             bindings::readl_relaxed(addr as *const _)
         }
     }
+
+    /// Reads the register through a bounds-checked [`IoMem`] mapping.
+    ///
+    /// Unlike [`read`](Self::read), which does raw pointer math on an unchecked `*const c_void`,
+    /// this shares the `IoMem` code path and so can never read outside the mapped BAR.
+    pub(crate) fn read_reg(&self, iomem: &IoMem) -> Result<u32> {
+        iomem.readl_relaxed(self.0 as usize)
+    }
+
+    /// Writes `value` to the register through a bounds-checked [`IoMem`] mapping.
+    pub(crate) fn write_reg(&self, iomem: &IoMem, value: u32) -> Result {
+        iomem.writel_relaxed(value, self.0 as usize)
+    }
+
+    /// Polls the register through `iomem` until `(value & mask) == expected` or the timeout
+    /// elapses. See [`IoMem::poll_until`] for the semantics of `sleep_us`/`timeout_us`.
+    pub(crate) fn poll_until(
+        &self,
+        iomem: &IoMem,
+        mask: u32,
+        expected: u32,
+        sleep_us: u64,
+        timeout_us: u64,
+    ) -> Result {
+        iomem.poll_until(self.0 as usize, mask, expected, sleep_us, timeout_us)
+    }
 }
 
 impl Add for GpuRegister {
@@ -57,28 +92,47 @@ pub(crate) const fn genmask(high: u64, low: u64) -> u64 {
     ((1 << (high - low + 1)) - 1) << low
 }
 
-pub(crate) const GPU_ID: GpuRegister = GpuRegister(0x0);
-pub(crate) const fn gpu_arch_major(x: u64) -> GpuRegister {
-    GpuRegister((x) >> 28)
-}
-pub(crate) const fn gpu_arch_minor(x: u64) -> GpuRegister {
-    GpuRegister((x) & genmask(27, 24) >> 24)
-}
-pub(crate) const fn gpu_arch_rev(x: u64) -> GpuRegister {
-    GpuRegister((x) & genmask(23, 20) >> 20)
+/// Extracts the inclusive bit range `[high:low]` from `value`.
+///
+/// This replaces the hand-written `& genmask(..) >> ..` accessors, which had broken operator
+/// precedence (`>>` binds tighter than `&`, so they shifted the mask instead of the masked
+/// value). `high`/`low` are guarded as debug invariants.
+pub(crate) fn field(value: u64, high: u32, low: u32) -> u64 {
+    debug_assert!(high >= low);
+    debug_assert!(high < 64);
+    // `u64::MAX >> (63 - (high - low))` avoids the `1 << 64` overflow a full-width
+    // (`high=63, low=0`) field would hit with the naive `(1 << (high - low + 1)) - 1` mask.
+    (value >> low) & (u64::MAX >> (63 - (high - low)))
 }
-pub(crate) const fn gpu_prod_major(x: u64) -> GpuRegister {
-    GpuRegister((x) & genmask(19, 16) >> 16)
-}
-pub(crate) const fn gpu_ver_major(x: u64) -> GpuRegister {
-    GpuRegister((x) & genmask(15, 12) >> 12)
-}
-pub(crate) const fn gpu_ver_minor(x: u64) -> GpuRegister {
-    GpuRegister((x) & genmask(11, 4) >> 4)
+
+/// Decoded view of the `GPU_ID` register.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct GpuId {
+    pub(crate) arch_major: u8,
+    pub(crate) arch_minor: u8,
+    pub(crate) arch_rev: u8,
+    pub(crate) prod_major: u8,
+    pub(crate) ver_major: u8,
+    pub(crate) ver_minor: u8,
+    pub(crate) ver_status: u8,
 }
-pub(crate) const fn gpu_ver_status(x: u64) -> GpuRegister {
-    GpuRegister(x & genmask(3, 0))
+
+impl GpuId {
+    pub(crate) fn new(value: u32) -> Self {
+        let value = value as u64;
+        Self {
+            arch_major: field(value, 31, 28) as u8,
+            arch_minor: field(value, 27, 24) as u8,
+            arch_rev: field(value, 23, 20) as u8,
+            prod_major: field(value, 19, 16) as u8,
+            ver_major: field(value, 15, 12) as u8,
+            ver_minor: field(value, 11, 4) as u8,
+            ver_status: field(value, 3, 0) as u8,
+        }
+    }
 }
+
+pub(crate) const GPU_ID: GpuRegister = GpuRegister(0x0);
 pub(crate) const GPU_L2_FEATURES: GpuRegister = GpuRegister(0x4);
 pub(crate) const fn gpu_l2_features_line_size(x: u64) -> GpuRegister {
     GpuRegister(1 << ((x) & genmask(7, 0)))
@@ -88,11 +142,22 @@ pub(crate) const GPU_TILER_FEATURES: GpuRegister = GpuRegister(0xc);
 pub(crate) const GPU_MEM_FEATURES: GpuRegister = GpuRegister(0x10);
 pub(crate) const GROUPS_L2_COHERENT: GpuRegister = GpuRegister(bit(0));
 pub(crate) const GPU_MMU_FEATURES: GpuRegister = GpuRegister(0x14);
-pub(crate) const fn gpu_mmu_features_va_bits(x: u64) -> GpuRegister {
-    GpuRegister((x) & genmask(7, 0))
+
+/// Decoded view of the `GPU_MMU_FEATURES` register.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MmuFeatures {
+    pub(crate) va_bits: u8,
+    pub(crate) pa_bits: u8,
 }
-pub(crate) const fn gpu_mmu_features_pa_bits(x: u64) -> GpuRegister {
-    GpuRegister(((x) >> 8) & genmask(7, 0))
+
+impl MmuFeatures {
+    pub(crate) fn new(value: u32) -> Self {
+        let value = value as u64;
+        Self {
+            va_bits: field(value, 7, 0) as u8,
+            pa_bits: field(value, 15, 8) as u8,
+        }
+    }
 }
 pub(crate) const GPU_AS_PRESENT: GpuRegister = GpuRegister(0x18);
 pub(crate) const GPU_CSF_ID: GpuRegister = GpuRegister(0x1c);
@@ -286,3 +351,127 @@ pub(crate) const fn csf_doorbell(i: u64) -> GpuRegister {
     GpuRegister(0x80000 + ((i) * 0x10000))
 }
 pub(crate) const CSF_GLB_DOORBELL_ID: GpuRegister = GpuRegister(0);
+
+/// The state-dump format produced by [`dump`].
+///
+/// The blob is self-describing: it starts with a [`DumpHeader`] carrying a magic number and a
+/// version, followed by `num_entries` [`RegEntry`] triples of `(offset, name, value)`. A decoder
+/// can therefore render every register without hardcoding the producer's register list.
+mod coredump {
+    /// "PREG", distinct from the `dump.rs` coredump magic so a decoder can tell the two
+    /// self-describing formats apart.
+    pub(super) const MAGIC: u32 = 0x47455250;
+    /// Bumped whenever the on-disk layout of the register dump changes.
+    pub(super) const VERSION: u32 = 1;
+    /// Bytes reserved for a register's symbolic name in a [`super::RegEntry`].
+    pub(super) const NAME_LEN: usize = 24;
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub(crate) struct DumpHeader {
+    magic: u32,
+    version: u32,
+    num_entries: u32,
+    padding: u32,
+}
+
+// SAFETY: `DumpHeader` is a `repr(C)` aggregate of POD fields.
+unsafe impl AsBytes for DumpHeader {}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub(crate) struct RegEntry {
+    offset: u64,
+    value: u32,
+    padding: u32,
+    name: [u8; coredump::NAME_LEN],
+}
+
+// SAFETY: `RegEntry` is a `repr(C)` aggregate of POD fields.
+unsafe impl AsBytes for RegEntry {}
+
+impl RegEntry {
+    fn new(reg: GpuRegister, name: &str, value: u32) -> Self {
+        let mut buf = [0u8; coredump::NAME_LEN];
+        let src = name.as_bytes();
+        let len = core::cmp::min(src.len(), coredump::NAME_LEN - 1);
+        buf[..len].copy_from_slice(&src[..len]);
+        Self {
+            offset: reg.0,
+            value,
+            padding: 0,
+            name: buf,
+        }
+    }
+}
+
+/// The GPU-level registers captured in a state dump, with their symbolic names.
+pub(crate) const REGISTRY: [(GpuRegister, &str); 10] = [
+    (GPU_ID, "GPU_ID"),
+    (GPU_STATUS, "GPU_STATUS"),
+    (GPU_FAULT_STATUS, "GPU_FAULT_STATUS"),
+    (GPU_FAULT_ADDR_LO, "GPU_FAULT_ADDR_LO"),
+    (GPU_FAULT_ADDR_HI, "GPU_FAULT_ADDR_HI"),
+    (MCU_STATUS, "MCU_STATUS"),
+    (JOB_INT_MASK, "JOB_INT_MASK"),
+    (JOB_INT_STAT, "JOB_INT_STAT"),
+    (MMU_INT_MASK, "MMU_INT_MASK"),
+    (MMU_INT_STAT, "MMU_INT_STAT"),
+];
+
+/// The per-address-space fault registers captured for every present address space.
+fn as_fault_registers(as_: u64) -> [(GpuRegister, &'static str); 5] {
+    [
+        (as_faultstatus(as_), "AS_FAULTSTATUS"),
+        (as_faultaddress_lo(as_), "AS_FAULTADDRESS_LO"),
+        (as_faultaddress_hi(as_), "AS_FAULTADDRESS_HI"),
+        (as_faultextra_lo(as_), "AS_FAULTEXTRA_LO"),
+        (as_faultextra_hi(as_), "AS_FAULTEXTRA_HI"),
+    ]
+}
+
+/// Snapshots the GPU register state through `iomem` into a self-describing blob and hands it to
+/// the kernel's devcoredump mechanism for `dev`.
+///
+/// Only the address spaces reported present in `GPU_AS_PRESENT` are read, so the dump never
+/// touches the undefined MMIO of an absent address space.
+pub(crate) fn dump(dev: *mut bindings::device, iomem: &IoMem) -> Result {
+    let as_present = GPU_AS_PRESENT.read_reg(iomem)?;
+    let as_count = as_present.count_ones() as usize;
+    let num_entries = REGISTRY.len() + as_count * as_fault_registers(0).len();
+
+    let size =
+        core::mem::size_of::<DumpHeader>() + num_entries * core::mem::size_of::<RegEntry>();
+    let mut buf = VmallocBuffer::new(size)?;
+
+    buf.append(&DumpHeader {
+        magic: coredump::MAGIC,
+        version: coredump::VERSION,
+        num_entries: num_entries as u32,
+        padding: 0,
+    })?;
+
+    for (reg, name) in &REGISTRY {
+        let value = reg.read_reg(iomem)?;
+        buf.append(&RegEntry::new(*reg, name, value))?;
+    }
+
+    for as_ in 0..u32::BITS {
+        if as_present & (1 << as_) == 0 {
+            continue;
+        }
+
+        for (reg, name) in &as_fault_registers(as_ as u64) {
+            let value = reg.read_reg(iomem)?;
+            buf.append(&RegEntry::new(*reg, name, value))?;
+        }
+    }
+
+    let (mem, size) = buf.into_raw();
+    // SAFETY: `mem` is a valid pointer to a valid allocation of `size` bytes. `dev_coredumpv`
+    // takes ownership of the allocation and frees it with `vfree`.
+    unsafe { bindings::dev_coredumpv(dev, mem.as_ptr() as _, size, bindings::GFP_KERNEL) };
+
+    Ok(())
+}
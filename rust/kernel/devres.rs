@@ -15,8 +15,10 @@ use crate::{
     types::ARef,
 };
 
+use core::cell::Cell;
 use core::ffi::c_void;
-use core::ops::Deref;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
 
 #[pin_data]
 struct DevresInner<T> {
@@ -128,6 +130,40 @@ impl<T> Devres<T> {
         // at `devres_callback<T>`'s address from `Devres::new` or `Devres::drop`.
         Ok(Devres { inner, callback })
     }
+
+    /// Registers `data` as a purely device-managed resource, without returning a retained handle.
+    ///
+    /// The data is boxed and handed to a devres callback that reconstructs and drops the box on
+    /// unbind; the Rust-side ownership is then leaked, so the resource lives exactly until the
+    /// device unbinds. This matches the common `devm_add_action_or_reset` pattern for resources a
+    /// driver never touches again at runtime.
+    ///
+    /// If registering the callback fails, `data` is dropped rather than leaked.
+    pub fn new_foreign_owned(dev: ARef<Device>, data: T, flags: Flags) -> Result<()> {
+        let data = Box::new(data, flags)?;
+        let ptr = Box::into_raw(data);
+
+        // SAFETY: `dev` is valid; `ptr` is a valid `Box<T>` allocation that the callback becomes
+        // the sole owner of on success.
+        let ret = unsafe {
+            bindings::devm_add_action(dev.as_raw(), Some(foreign_callback::<T>), ptr as *mut c_void)
+        };
+
+        if ret != 0 {
+            // Registration failed, so no callback will ever run: reclaim and drop the box here.
+            // SAFETY: `ptr` came from `Box::into_raw` just above and ownership was not transferred.
+            drop(unsafe { Box::from_raw(ptr) });
+            return Err(Error::from_errno(ret));
+        }
+
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn foreign_callback<T>(ptr: *mut c_void) {
+    // SAFETY: `ptr` was produced by `Box::into_raw` in `new_foreign_owned` and this callback is
+    // the sole owner, so reconstructing the box exactly once is sound.
+    drop(unsafe { Box::from_raw(ptr as *mut T) });
 }
 
 impl<T> Deref for Devres<T> {
@@ -149,3 +185,198 @@ impl<T> Drop for Devres<T> {
         }
     }
 }
+
+/// State of a [`DevresGroup`] in the devres group lifecycle.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GroupState {
+    Open,
+    Closed,
+    Released,
+}
+
+/// Backing allocation of a [`DevresGroup`]; its heap address is used as the group `id`.
+struct DevresGroupInner {
+    dev: ARef<Device>,
+    state: Cell<GroupState>,
+}
+
+/// A devres group, batching several [`Device`] bound resources so they can be released together.
+///
+/// This wraps the kernel's `devres_open_group`/`devres_close_group`/`devres_release_group`. Any
+/// device managed resource registered on the same [`Device`] while the group is open (for example
+/// via [`add`](DevresGroup::add)) becomes a member of the group.
+///
+/// [`release`](DevresGroup::release) tears every member down immediately and in reverse
+/// registration order, which is what a probe-failure rollback path wants. Dropping the group
+/// without releasing simply leaves the members bound to the device's normal unbind lifetime.
+///
+/// # Invariants
+///
+/// The group `id` — the address of the boxed [`DevresGroupInner`] — is stable and unique for the
+/// lifetime of the group, and the devres core never dereferences it.
+pub struct DevresGroup {
+    inner: Pin<Box<DevresGroupInner>>,
+}
+
+impl DevresGroup {
+    /// Opens a new devres group on `dev`.
+    pub fn new(dev: ARef<Device>, flags: Flags) -> Result<Self> {
+        let inner = Box::pin_init(
+            pin_init!(DevresGroupInner {
+                dev: dev,
+                state: Cell::new(GroupState::Open),
+            }),
+            flags,
+        )?;
+
+        let id = &*inner as *const DevresGroupInner as *mut c_void;
+
+        // SAFETY: `inner.dev` is valid; `id` is a stable, unique token owned by `inner`.
+        let ret = unsafe { bindings::devres_open_group(inner.dev.as_raw(), id, flags.as_raw()) };
+        if ret.is_null() {
+            return Err(ENOMEM);
+        }
+
+        Ok(Self { inner })
+    }
+
+    fn id(&self) -> *mut c_void {
+        &*self.inner as *const DevresGroupInner as *mut c_void
+    }
+
+    /// Registers a [`Device`] bound resource as a member of this (open) group.
+    ///
+    /// Returns the [`Devres`] handle for the resource, exactly as [`Devres::new`] would.
+    pub fn add<T>(&self, data: T, flags: Flags) -> Result<Devres<T>> {
+        Devres::new(self.inner.dev.clone(), data, flags)
+    }
+
+    /// Registers a raw devres action as a member of this (open) group.
+    ///
+    /// # Safety
+    ///
+    /// `callback` and `data` must satisfy the same requirements as `devm_add_action`: the callback
+    /// must be safe to call with `data` once, when the action runs.
+    pub unsafe fn add_action(
+        &self,
+        callback: unsafe extern "C" fn(*mut c_void),
+        data: *mut c_void,
+    ) -> Result {
+        // SAFETY: `inner.dev` is valid; the callback/data contract is guaranteed by the caller.
+        let ret =
+            unsafe { bindings::devm_add_action(self.inner.dev.as_raw(), Some(callback), data) };
+        if ret != 0 {
+            return Err(Error::from_errno(ret));
+        }
+
+        Ok(())
+    }
+
+    /// Closes the group, so subsequently registered resources are no longer members.
+    pub fn close(&self) {
+        if self.inner.state.get() == GroupState::Open {
+            // SAFETY: `inner.dev` is valid and the group identified by `id` is open.
+            unsafe { bindings::devres_close_group(self.inner.dev.as_raw(), self.id()) };
+            self.inner.state.set(GroupState::Closed);
+        }
+    }
+
+    /// Releases every member of the group immediately, in reverse registration order.
+    ///
+    /// This is idempotent: calling it more than once (or a later device unbind) does not release
+    /// the members twice.
+    pub fn release(&self) {
+        if self.inner.state.get() != GroupState::Released {
+            // SAFETY: `inner.dev` is valid and the group identified by `id` has not been released.
+            unsafe { bindings::devres_release_group(self.inner.dev.as_raw(), self.id()) };
+            self.inner.state.set(GroupState::Released);
+        }
+    }
+}
+
+/// A device-managed smart pointer whose backing storage is allocated with `devm_kmalloc`.
+///
+/// The memory is freed automatically when the [`Device`] unbinds, so [`Devm`] deliberately does
+/// **not** free it or run a devres-remove in its [`Drop`]. Because `devm_kmalloc`'d storage may be
+/// dereferenced only while the device is bound, the value is wrapped in a [`Revocable`] (as
+/// [`Devres`] does): use [`try_access`](Revocable::try_access) to obtain a guard that returns
+/// `None` once the device has unbound, preventing use-after-free.
+///
+/// # Invariants
+///
+/// `data` points to a live `Revocable<T>` allocated in `dev`'s `devm_kmalloc` arena for as long as
+/// `dev` is bound.
+pub struct Devm<T> {
+    dev: ARef<Device>,
+    data: NonNull<Revocable<T>>,
+}
+
+impl<T> Devm<T> {
+    /// Allocates storage for `T` through `devm_kmalloc` and initializes it in place.
+    pub fn new(dev: ARef<Device>, data: T, flags: Flags) -> Result<Self> {
+        let size = core::mem::size_of::<Revocable<T>>();
+
+        // SAFETY: `dev` is valid; `devm_kmalloc` returns either null or a pointer to `size` bytes
+        // owned by the device's arena.
+        let ptr = unsafe { bindings::devm_kmalloc(dev.as_raw(), size, flags.as_raw()) };
+        let ptr = NonNull::new(ptr as *mut Revocable<T>).ok_or(ENOMEM)?;
+
+        // Initialize the `Revocable<T>` in place. On failure the allocation stays device-managed
+        // and is reclaimed at unbind, so nothing leaks here.
+        let init = Revocable::new(data);
+        // SAFETY: `ptr` is a valid, suitably-sized and -aligned, uninitialized slot.
+        unsafe { init.__pinned_init(ptr.as_ptr())? };
+
+        // Register a devres action that revokes the `Revocable` at unbind. This runs *before* the
+        // `devm_kmalloc` arena is freed, so any outstanding [`try_access`](Revocable::try_access)
+        // guard is invalidated while the backing memory is still live, preventing use-after-free.
+        // SAFETY: `dev` is valid and `ptr` points to a live `Revocable<T>` in `dev`'s arena that
+        // the callback only reads through `revoke()`.
+        let ret = unsafe {
+            bindings::devm_add_action(
+                dev.as_raw(),
+                Some(devm_revoke_callback::<T>),
+                ptr.as_ptr() as *mut c_void,
+            )
+        };
+
+        if ret != 0 {
+            // Registration failed, so the callback will never run: revoke now. The `Revocable`
+            // storage itself stays device-managed and is reclaimed at unbind.
+            // SAFETY: `ptr` points to the live `Revocable<T>` just initialized above.
+            unsafe { ptr.as_ref().revoke() };
+            return Err(Error::from_errno(ret));
+        }
+
+        Ok(Self { dev, data: ptr })
+    }
+}
+
+unsafe extern "C" fn devm_revoke_callback<T>(ptr: *mut c_void) {
+    let revocable = ptr as *const Revocable<T>;
+    // SAFETY: `ptr` was registered in `Devm::new` and points to a live `Revocable<T>` that the
+    // arena has not yet freed when this unbind callback runs.
+    unsafe { &*revocable }.revoke();
+}
+
+impl<T> Deref for Devm<T> {
+    type Target = Revocable<T>;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: By the type invariants `data` points to a live `Revocable<T>`.
+        unsafe { self.data.as_ref() }
+    }
+}
+
+impl<T> DerefMut for Devm<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: By the type invariants `data` points to a live `Revocable<T>` and `&mut self`
+        // guarantees exclusive access.
+        unsafe { self.data.as_mut() }
+    }
+}
+
+// SAFETY: `Devm` owns a device-bound `Revocable<T>` and only exposes it through revocable guards.
+unsafe impl<T: Send> Send for Devm<T> {}
+// SAFETY: See above.
+unsafe impl<T: Sync> Sync for Devm<T> {}
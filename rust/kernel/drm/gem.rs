@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+//! DRM GEM API
+//!
+//! C header: [`include/drm/drm_gem.h`](../../../../include/drm/drm_gem.h)
+
+use crate::{
+    bindings,
+    error::{Error, Result},
+};
+use core::ptr::NonNull;
+
+/// A lightweight reference to a bare `drm_gem_object`.
+///
+/// This wrapper is not typed on a driver and does not `container_of!` the embedded object back
+/// into a driver-specific Rust `T`. It is meant for drivers
+/// that are still C and only expose individual Rust functions called from C: such code has a bare
+/// `*mut bindings::drm_gem_object` but neither a `drv::Driver` nor the concrete GEM subtype, so the
+/// typed abstraction is unusable. [`RawGemObject`] gives those functions safe access to the common
+/// GEM operations (size, refcounting, mapping) while the conversion is done incrementally.
+///
+/// # Invariants
+///
+/// The wrapped pointer is non-null and valid for the lifetime of the `RawGemObject`.
+pub struct RawGemObject(NonNull<bindings::drm_gem_object>);
+
+impl RawGemObject {
+    /// Wraps an existing `drm_gem_object` pointer.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be non-null and point to a valid `drm_gem_object` with a non-zero reference
+    /// count that stays alive for the lifetime of the returned `RawGemObject`. This wrapper does
+    /// not take ownership of a reference; use [`get`](Self::get)/[`put`](Self::put) to manage the
+    /// count explicitly.
+    pub unsafe fn from_raw(ptr: *mut bindings::drm_gem_object) -> Self {
+        // SAFETY: By the safety requirements `ptr` is non-null.
+        Self(unsafe { NonNull::new_unchecked(ptr) })
+    }
+
+    fn as_raw(&self) -> *mut bindings::drm_gem_object {
+        self.0.as_ptr()
+    }
+
+    /// Returns the size of the GEM object's backing storage in bytes.
+    pub fn size(&self) -> usize {
+        // SAFETY: The pointer is valid by the type invariants.
+        unsafe { (*self.as_raw()).size }
+    }
+
+    /// Acquires an additional reference to the object.
+    pub fn get(&self) {
+        // SAFETY: The pointer is valid by the type invariants.
+        unsafe { bindings::drm_gem_object_get(self.as_raw()) };
+    }
+
+    /// Releases a reference previously acquired with [`get`](Self::get).
+    pub fn put(&self) {
+        // SAFETY: The pointer is valid by the type invariants.
+        unsafe { bindings::drm_gem_object_put(self.as_raw()) };
+    }
+
+    /// Maps the object's backing pages into the kernel address space.
+    ///
+    /// The returned [`VmapGuard`] unmaps the pages when dropped.
+    pub fn vmap(&self) -> Result<VmapGuard<'_>> {
+        let mut map = bindings::iosys_map::default();
+
+        // SAFETY: The pointer is valid by the type invariants and `map` is a valid place to store
+        // the mapping.
+        let ret = unsafe { bindings::drm_gem_vmap_unlocked(self.as_raw(), &mut map) };
+        if ret != 0 {
+            return Err(Error::from_errno(ret));
+        }
+
+        Ok(VmapGuard { obj: self, map })
+    }
+}
+
+/// An RAII guard for a kernel-space mapping of a [`RawGemObject`]'s backing pages.
+///
+/// Created by [`RawGemObject::vmap`]; the mapping is torn down on drop.
+pub struct VmapGuard<'a> {
+    obj: &'a RawGemObject,
+    map: bindings::iosys_map,
+}
+
+impl VmapGuard<'_> {
+    /// Returns the mapped pages as a byte slice.
+    pub fn as_slice(&self) -> &[u8] {
+        // SAFETY: The mapping is valid for as long as the guard lives and spans the object's size.
+        unsafe {
+            core::slice::from_raw_parts(
+                self.map.__bindgen_anon_1.vaddr as *const u8,
+                self.obj.size(),
+            )
+        }
+    }
+}
+
+impl Drop for VmapGuard<'_> {
+    fn drop(&mut self) {
+        // SAFETY: `obj` is valid and was mapped by `vmap`; `map` is the mapping it returned.
+        unsafe { bindings::drm_gem_vunmap_unlocked(self.obj.as_raw(), &mut self.map) };
+    }
+}
@@ -1,7 +1,10 @@
 // SPDX-License-Identifier: GPL-2.0
 
 use crate::bindings;
-use crate::error::{code::EINVAL, Result};
+use crate::error::{
+    code::{EINVAL, ETIMEDOUT},
+    Result,
+};
 
 /// IO-mapped memory, starting at the base pointer @ioptr and spanning @malxen bytes.
 ///
@@ -22,11 +25,57 @@ impl IoMem {
     }
 
     fn get_io_addr(&self, offset: usize, len: usize) -> Result<usize> {
-        if offset + len > self.maxlen {
+        // Use checked arithmetic so a large `offset`/`len` cannot wrap around and pass the bounds
+        // check (e.g. on 32-bit), which would let a caller read or write outside the mapping.
+        let end = offset.checked_add(len).ok_or(EINVAL)?;
+        if end > self.maxlen {
             return Err(EINVAL);
         }
 
-        Ok(self.ioptr + offset)
+        self.ioptr.checked_add(offset).ok_or(EINVAL)
+    }
+
+    /// Polls the 32-bit register at `offset` until `(value & mask) == expected`, or until
+    /// `timeout_us` microseconds have elapsed.
+    ///
+    /// Between reads the caller sleeps for `sleep_us` microseconds, or busy-waits with `cpu_relax`
+    /// when `sleep_us` is zero. The elapsed time is measured with `ktime_get`, so a wedged device
+    /// can never hang the caller forever. Returns `Err(ETIMEDOUT)` if the condition is not met in
+    /// time.
+    pub fn poll_until(
+        &self,
+        offset: usize,
+        mask: u32,
+        expected: u32,
+        sleep_us: u64,
+        timeout_us: u64,
+    ) -> Result {
+        // SAFETY: `ktime_get` is always safe to call.
+        let start = unsafe { bindings::ktime_get() };
+
+        loop {
+            if self.readl_relaxed(offset)? & mask == expected {
+                return Ok(());
+            }
+
+            // SAFETY: `ktime_get` is always safe to call.
+            let elapsed_ns = unsafe { bindings::ktime_get() } - start;
+            if elapsed_ns as u64 >= timeout_us.saturating_mul(1000) {
+                // One last read to close the window between the check above and the timeout.
+                if self.readl_relaxed(offset)? & mask == expected {
+                    return Ok(());
+                }
+                return Err(ETIMEDOUT);
+            }
+
+            if sleep_us > 0 {
+                // SAFETY: FFI call sleeping for the requested range of microseconds.
+                unsafe { bindings::usleep_range(sleep_us, sleep_us.saturating_mul(2)) };
+            } else {
+                // SAFETY: `cpu_relax` is always safe to call.
+                unsafe { bindings::cpu_relax() };
+            }
+        }
     }
 
     pub fn readb(&self, offset: usize) -> Result<u8> {
@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: GPL-2.0
+
+//! Networking core abstractions.
+//!
+//! C header: [`include/linux/netdevice.h`](../../../../include/linux/netdevice.h)
+
+use crate::{
+    bindings,
+    device::Device,
+    devres::Devres,
+    error::{code::*, to_result, Result},
+    alloc::Flags,
+    types::ARef,
+};
+use core::ptr::NonNull;
+
+/// A registered `net_device`.
+///
+/// The registration is undone with `unregister_netdev` when this is dropped, which is what lets
+/// [`Registration`] tie it to the device lifetime.
+///
+/// NOTE: A full safe `net_device` abstraction is not in the kernel crate yet; until it lands this
+/// wraps the raw `bindings::net_device` pointer directly. Once the typed abstraction exists this
+/// should be parameterized over it.
+struct Netdev(NonNull<bindings::net_device>);
+
+impl Drop for Netdev {
+    fn drop(&mut self) {
+        // SAFETY: `self.0` is a device that was successfully registered in `Registration::new` and
+        // has not been unregistered yet.
+        unsafe { bindings::unregister_netdev(self.0.as_ptr()) };
+    }
+}
+
+// SAFETY: `Netdev` only holds a pointer to a C `net_device`, safe to move between threads.
+unsafe impl Send for Netdev {}
+
+/// A [`Devres`]-managed `register_netdev`/`unregister_netdev` pairing.
+///
+/// Registering through [`Registration::new`] guarantees the device is unregistered on either the
+/// handle being dropped or the owning [`Device`] being unbound, whichever happens first — exactly
+/// the revocation discipline [`Devres`] provides for every other device-bound resource. This
+/// closes the bug class of leaking a registered `net_device` when probe partially fails.
+pub struct Registration(Devres<Netdev>);
+
+impl Registration {
+    /// Registers `netdev` and binds its unregistration to `dev`'s lifetime.
+    ///
+    /// # Safety
+    ///
+    /// `netdev` must be a valid, not-yet-registered `net_device` that stays alive at least until
+    /// it is unregistered.
+    pub unsafe fn new(
+        dev: ARef<Device>,
+        netdev: *mut bindings::net_device,
+        flags: Flags,
+    ) -> Result<Self> {
+        let netdev = NonNull::new(netdev).ok_or(EINVAL)?;
+
+        // SAFETY: `netdev` is valid and not yet registered, per the safety requirements.
+        to_result(unsafe { bindings::register_netdev(netdev.as_ptr()) })?;
+
+        // On any failure past this point, `Netdev`'s `Drop` unregisters the device for us.
+        let devres = Devres::new(dev, Netdev(netdev), flags)?;
+        Ok(Self(devres))
+    }
+}
@@ -299,6 +299,52 @@ pub struct Bar {
     num: u8,
 }
 
+/// The raw resource flags of a PCI BAR, as reported by `pci_resource_flags`.
+#[derive(Clone, Copy)]
+pub struct BarFlags(u64);
+
+impl BarFlags {
+    /// Returns `true` if the BAR lives in I/O port space rather than memory space.
+    pub fn is_io(&self) -> bool {
+        self.0 & bindings::IORESOURCE_IO as u64 != 0
+    }
+
+    fn is_64bit(&self) -> bool {
+        self.0 & bindings::IORESOURCE_MEM_64 as u64 != 0
+    }
+
+    fn is_prefetchable(&self) -> bool {
+        self.0 & bindings::IORESOURCE_PREFETCH as u64 != 0
+    }
+
+    /// Decodes the flags into a [`BarKind`].
+    pub fn kind(&self) -> BarKind {
+        if self.is_io() {
+            BarKind::Io
+        } else if self.is_64bit() {
+            BarKind::Mem64 {
+                prefetchable: self.is_prefetchable(),
+            }
+        } else {
+            BarKind::Mem32
+        }
+    }
+}
+
+/// The kind of address space a PCI BAR decodes, derived from its [`BarFlags`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BarKind {
+    /// An I/O port space BAR.
+    Io,
+    /// A 32-bit memory space BAR.
+    Mem32,
+    /// A 64-bit memory space BAR.
+    Mem64 {
+        /// Whether the region is prefetchable.
+        prefetchable: bool,
+    },
+}
+
 impl Bar {
     fn new(pdev: Device, num: u8, name: &CStr) -> Result<Self> {
         let barnr = num as i32;
@@ -308,6 +354,12 @@ impl Bar {
             return Err(ENOMEM);
         }
 
+        // I/O-space BARs cannot be mapped with `pci_iomap` the way memory BARs are; reject them
+        // here instead of letting the mapping fail opaquely below.
+        if pdev.resource_flags(num)?.is_io() {
+            return Err(EINVAL);
+        }
+
         // SAFETY:
         // `pdev` is always valid.
         // `barnr` is checked for validity at the top of the function.
@@ -367,6 +419,16 @@ impl Bar {
         }
     }
 
+    /// Returns the [`BarKind`] of this BAR.
+    pub fn kind(&self) -> Result<BarKind> {
+        Ok(self.pdev.resource_flags(self.num)?.kind())
+    }
+
+    /// Returns `true` if this BAR decodes I/O port space.
+    pub fn is_io(&self) -> Result<bool> {
+        Ok(self.pdev.resource_flags(self.num)?.is_io())
+    }
+
     fn release(&self) {
         // SAFETY:
         // Safe because `self` always contains a refcounted device that belongs
@@ -390,6 +452,36 @@ impl Deref for Bar {
     }
 }
 
+/// The decoded standard PCI configuration header.
+///
+/// This collects the well-known fixed offsets of the type 0 configuration space that a driver
+/// typically consults while probing or quirking a device. See [`Device::config_header`].
+#[derive(Clone, Copy, Debug)]
+pub struct ConfigHeader {
+    /// Vendor ID (offset 0x00).
+    pub vendor_id: u16,
+    /// Device ID (offset 0x02).
+    pub device_id: u16,
+    /// Revision ID (offset 0x08).
+    pub revision_id: u8,
+    /// Programming interface byte (offset 0x09).
+    pub prog_if: u8,
+    /// Sub-class code (offset 0x0A).
+    pub subclass: u8,
+    /// Base-class code (offset 0x0B).
+    pub class: u8,
+    /// Header type (offset 0x0E).
+    pub header_type: u8,
+    /// Subsystem vendor ID (offset 0x2C).
+    pub subsystem_vendor_id: u16,
+    /// Subsystem device ID (offset 0x2E).
+    pub subsystem_device_id: u16,
+    /// Interrupt line (offset 0x3C).
+    pub interrupt_line: u8,
+    /// Interrupt pin (offset 0x3D).
+    pub interrupt_pin: u8,
+}
+
 impl Device {
     /// Create a PCI Device instance from an existing `device::Device`.
     ///
@@ -433,6 +525,121 @@ impl Device {
         Ok(unsafe { bindings::pci_resource_len(self.as_raw(), bar.into()) })
     }
 
+    /// Returns the resource flags of the given PCI BAR.
+    pub fn resource_flags(&self, bar: u8) -> Result<BarFlags> {
+        if !Bar::index_is_valid(bar) {
+            return Err(EINVAL);
+        }
+
+        // SAFETY: Safe as by the type invariant.
+        let flags = unsafe { bindings::pci_resource_flags(self.as_raw(), bar.into()) };
+        Ok(BarFlags(flags as u64))
+    }
+
+    /// Reads a byte from the device's configuration space at `offset`.
+    pub fn read_config_u8(&self, offset: u8) -> Result<u8> {
+        let mut val: u8 = 0;
+        // SAFETY: `self.as_raw()` is a valid `pci_dev` by the type invariants and `val` is a valid
+        // place to store the result.
+        let ret = unsafe { bindings::pci_read_config_byte(self.as_raw(), offset.into(), &mut val) };
+        // SAFETY: FFI call converting a `PCIBIOS_*` code into an `errno`.
+        to_result(unsafe { bindings::pcibios_err_to_errno(ret) })?;
+        Ok(val)
+    }
+
+    /// Reads a word from the device's configuration space at `offset`.
+    pub fn read_config_u16(&self, offset: u8) -> Result<u16> {
+        let mut val: u16 = 0;
+        // SAFETY: `self.as_raw()` is a valid `pci_dev` by the type invariants and `val` is a valid
+        // place to store the result.
+        let ret = unsafe { bindings::pci_read_config_word(self.as_raw(), offset.into(), &mut val) };
+        // SAFETY: FFI call converting a `PCIBIOS_*` code into an `errno`.
+        to_result(unsafe { bindings::pcibios_err_to_errno(ret) })?;
+        Ok(val)
+    }
+
+    /// Reads a dword from the device's configuration space at `offset`.
+    pub fn read_config_u32(&self, offset: u8) -> Result<u32> {
+        let mut val: u32 = 0;
+        // SAFETY: `self.as_raw()` is a valid `pci_dev` by the type invariants and `val` is a valid
+        // place to store the result.
+        let ret = unsafe { bindings::pci_read_config_dword(self.as_raw(), offset.into(), &mut val) };
+        // SAFETY: FFI call converting a `PCIBIOS_*` code into an `errno`.
+        to_result(unsafe { bindings::pcibios_err_to_errno(ret) })?;
+        Ok(val)
+    }
+
+    /// Writes a byte to the device's configuration space at `offset`.
+    pub fn write_config_u8(&self, offset: u8, val: u8) -> Result {
+        // SAFETY: `self.as_raw()` is a valid `pci_dev` by the type invariants.
+        let ret = unsafe { bindings::pci_write_config_byte(self.as_raw(), offset.into(), val) };
+        // SAFETY: FFI call converting a `PCIBIOS_*` code into an `errno`.
+        to_result(unsafe { bindings::pcibios_err_to_errno(ret) })
+    }
+
+    /// Writes a word to the device's configuration space at `offset`.
+    pub fn write_config_u16(&self, offset: u8, val: u16) -> Result {
+        // SAFETY: `self.as_raw()` is a valid `pci_dev` by the type invariants.
+        let ret = unsafe { bindings::pci_write_config_word(self.as_raw(), offset.into(), val) };
+        // SAFETY: FFI call converting a `PCIBIOS_*` code into an `errno`.
+        to_result(unsafe { bindings::pcibios_err_to_errno(ret) })
+    }
+
+    /// Writes a dword to the device's configuration space at `offset`.
+    pub fn write_config_u32(&self, offset: u8, val: u32) -> Result {
+        // SAFETY: `self.as_raw()` is a valid `pci_dev` by the type invariants.
+        let ret = unsafe { bindings::pci_write_config_dword(self.as_raw(), offset.into(), val) };
+        // SAFETY: FFI call converting a `PCIBIOS_*` code into an `errno`.
+        to_result(unsafe { bindings::pcibios_err_to_errno(ret) })
+    }
+
+    /// Reads and decodes the standard configuration header of the device.
+    pub fn config_header(&self) -> Result<ConfigHeader> {
+        Ok(ConfigHeader {
+            vendor_id: self.read_config_u16(0x00)?,
+            device_id: self.read_config_u16(0x02)?,
+            revision_id: self.read_config_u8(0x08)?,
+            prog_if: self.read_config_u8(0x09)?,
+            subclass: self.read_config_u8(0x0a)?,
+            class: self.read_config_u8(0x0b)?,
+            header_type: self.read_config_u8(0x0e)?,
+            subsystem_vendor_id: self.read_config_u16(0x2c)?,
+            subsystem_device_id: self.read_config_u16(0x2e)?,
+            interrupt_line: self.read_config_u8(0x3c)?,
+            interrupt_pin: self.read_config_u8(0x3d)?,
+        })
+    }
+
+    /// Walks the capability list and returns the configuration-space offset of the first
+    /// capability matching `cap_id`, or `None` if the device has no such capability.
+    pub fn find_capability(&self, cap_id: u8) -> Option<u8> {
+        self.capabilities().find(|c| c.id == cap_id).map(|c| c.offset)
+    }
+
+    /// Returns an iterator over the device's PCI capabilities.
+    ///
+    /// The iterator yields each capability in list order. It is bounded to at most
+    /// [`Capabilities::MAX_CAPS`] iterations and treats pointers below 0x40 as terminators, so a
+    /// device advertising a malformed (e.g. self-referential) capability list cannot cause it to
+    /// loop forever.
+    pub fn capabilities(&self) -> Capabilities<'_> {
+        // A missing "Capabilities List" status bit, or an unreadable status register, means there
+        // is nothing to walk: start the iterator already terminated.
+        let ptr = match self.read_config_u16(0x06) {
+            Ok(status) if status & 0x0010 != 0 => self
+                .read_config_u8(0x34)
+                .map(|p| p & !0x3)
+                .unwrap_or(0),
+            _ => 0,
+        };
+
+        Capabilities {
+            dev: self,
+            next: ptr,
+            remaining: Capabilities::MAX_CAPS,
+        }
+    }
+
     /// Mapps an entire PCI-BAR after performing a region-request on it.
     pub fn iomap_region(&mut self, barnr: u8, name: &CStr) -> Result<Devres<Bar>> {
         let bar = Bar::new(self.clone(), barnr, name)?;
@@ -440,6 +647,138 @@ impl Device {
 
         Ok(devres)
     }
+
+    /// Allocates between `min` and `max` interrupt vectors of the type(s) selected by `flags`.
+    ///
+    /// The returned [`IrqVectors`] guard releases the vectors on drop or on device unbind,
+    /// whichever happens first.
+    pub fn alloc_irq_vectors(
+        &self,
+        min: u32,
+        max: u32,
+        flags: IrqFlags,
+    ) -> Result<Devres<IrqVectors>> {
+        // SAFETY: `self.as_raw()` is valid by the type invariants.
+        let ret = unsafe {
+            bindings::pci_alloc_irq_vectors(self.as_raw(), min, max, flags.to_raw())
+        };
+        if ret < 0 {
+            return Err(Error::from_errno(ret));
+        }
+
+        let vectors = IrqVectors {
+            pdev: self.clone(),
+            count: ret as u32,
+        };
+
+        Devres::new(self.0.clone(), vectors, GFP_KERNEL)
+    }
+}
+
+/// A single node of the PCI capability linked list, as yielded by [`Capabilities`].
+#[derive(Clone, Copy, Debug)]
+pub struct Capability {
+    /// Capability ID (the byte at `offset + 0`).
+    pub id: u8,
+    /// Configuration-space offset of this capability's header.
+    pub offset: u8,
+}
+
+/// Iterator over a [`Device`]'s PCI capability list.
+///
+/// Created by [`Device::capabilities`].
+pub struct Capabilities<'a> {
+    dev: &'a Device,
+    next: u8,
+    remaining: usize,
+}
+
+impl Capabilities<'_> {
+    /// The longest capability list the walker will follow before giving up, guarding against
+    /// malformed hardware with a cyclic list.
+    const MAX_CAPS: usize = 48;
+}
+
+impl Iterator for Capabilities<'_> {
+    type Item = Capability;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Pointers below 0x40 point into the standard header and are used as terminators.
+        if self.next < 0x40 || self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let offset = self.next;
+        let id = self.dev.read_config_u8(offset).ok()?;
+        self.next = self.dev.read_config_u8(offset + 1).ok()? & !0x3;
+
+        Some(Capability { id, offset })
+    }
+}
+
+/// The set of interrupt types [`Device::alloc_irq_vectors`] is allowed to allocate.
+#[derive(Clone, Copy)]
+pub enum IrqFlags {
+    /// Legacy (INTx) interrupts only.
+    Legacy,
+    /// MSI interrupts only.
+    Msi,
+    /// MSI-X interrupts only.
+    Msix,
+    /// Any supported interrupt type, preferring MSI-X, then MSI, then legacy.
+    All,
+}
+
+impl IrqFlags {
+    fn to_raw(self) -> u32 {
+        match self {
+            IrqFlags::Legacy => bindings::PCI_IRQ_LEGACY,
+            IrqFlags::Msi => bindings::PCI_IRQ_MSI,
+            IrqFlags::Msix => bindings::PCI_IRQ_MSIX,
+            IrqFlags::All => bindings::PCI_IRQ_ALL_TYPES,
+        }
+    }
+}
+
+/// An RAII guard for a set of interrupt vectors allocated on a [`Device`].
+///
+/// The vectors are released with `pci_free_irq_vectors` when the guard is dropped. As with
+/// [`Device::iomap_region`] the guard is handed out wrapped in a [`Devres`], so the vectors are
+/// also released should the device be unbound first.
+pub struct IrqVectors {
+    pdev: Device,
+    count: u32,
+}
+
+impl IrqVectors {
+    /// Returns the number of allocated vectors.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Returns the Linux IRQ number for the vector at `index`.
+    pub fn irq(&self, index: u32) -> Result<u32> {
+        if index >= self.count {
+            return Err(EINVAL);
+        }
+
+        // SAFETY: `pdev` is valid by the type invariants and `index` is within the allocated range.
+        let irq = unsafe { bindings::pci_irq_vector(self.pdev.as_raw(), index) };
+        if irq < 0 {
+            return Err(Error::from_errno(irq));
+        }
+
+        Ok(irq as u32)
+    }
+}
+
+impl Drop for IrqVectors {
+    fn drop(&mut self) {
+        // SAFETY: `pdev` is valid by the type invariants; freeing vectors that were successfully
+        // allocated in `alloc_irq_vectors`.
+        unsafe { bindings::pci_free_irq_vectors(self.pdev.as_raw()) };
+    }
 }
 
 impl AsRef<device::Device> for Device {
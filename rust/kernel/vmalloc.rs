@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: GPL-2.0
+
+//! A safe wrapper around a `vmalloc`-backed buffer.
+//!
+//! C header: [`include/linux/vmalloc.h`](../../../../include/linux/vmalloc.h)
+
+use crate::{
+    bindings,
+    error::{code::*, Result},
+    types::AsBytes,
+};
+use core::ptr::NonNull;
+
+/// The alignment enforced on every bump allocation handed out by [`VmallocBuffer`].
+const ALIGN: usize = 8;
+
+/// An RAII wrapper around a contiguous `vmalloc` allocation.
+///
+/// The buffer owns its backing memory and releases it with `vfree` on drop. On top of raw slice
+/// access it offers a checked bump allocator ([`append`](VmallocBuffer::append) /
+/// [`append_bytes`](VmallocBuffer::append_bytes)) that keeps every appended item 8-byte aligned,
+/// which is what large binary producers such as a device coredump need.
+pub struct VmallocBuffer {
+    mem: NonNull<u8>,
+    pos: usize,
+    capacity: usize,
+}
+
+impl VmallocBuffer {
+    /// Allocates a zeroed buffer of `size` bytes using `vzalloc`.
+    pub fn new(size: usize) -> Result<Self> {
+        if size == 0 || isize::try_from(size).is_err() {
+            return Err(EINVAL);
+        }
+
+        // SAFETY: FFI call to allocate `size` zeroed bytes; the returned pointer is checked below.
+        let mem = NonNull::new(unsafe { bindings::vzalloc(size) } as *mut u8).ok_or(ENOMEM)?;
+
+        Ok(Self {
+            mem,
+            pos: 0,
+            capacity: size,
+        })
+    }
+
+    /// Returns the total capacity of the buffer in bytes.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of bytes appended so far.
+    pub fn len(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns `true` if nothing has been appended yet.
+    pub fn is_empty(&self) -> bool {
+        self.pos == 0
+    }
+
+    /// Returns the whole backing allocation as a shared slice.
+    pub fn as_slice(&self) -> &[u8] {
+        // SAFETY: `mem` points at a valid `capacity`-byte allocation that was zero-initialized on
+        // creation and outlives the borrow.
+        unsafe { core::slice::from_raw_parts(self.mem.as_ptr(), self.capacity) }
+    }
+
+    /// Returns the whole backing allocation as a mutable slice.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: As for `as_slice`, and `&mut self` guarantees exclusive access.
+        unsafe { core::slice::from_raw_parts_mut(self.mem.as_ptr(), self.capacity) }
+    }
+
+    /// Reserves `size` 8-byte-aligned bytes and returns their offset, or `Err(ENOMEM)` when the
+    /// buffer is exhausted.
+    fn reserve(&mut self, size: usize) -> Result<usize> {
+        let size = size.checked_add(ALIGN - 1).ok_or(ENOMEM)? & !(ALIGN - 1);
+        let end = self.pos.checked_add(size).ok_or(ENOMEM)?;
+        if end > self.capacity {
+            return Err(ENOMEM);
+        }
+
+        let offset = self.pos;
+        self.pos = end;
+        Ok(offset)
+    }
+
+    /// Appends the bytes of `value` to the buffer, padded to an 8-byte boundary.
+    pub fn append<T: AsBytes>(&mut self, value: &T) -> Result {
+        self.append_bytes(value.as_bytes())
+    }
+
+    /// Appends a raw byte slice to the buffer, padded to an 8-byte boundary.
+    pub fn append_bytes(&mut self, bytes: &[u8]) -> Result {
+        let offset = self.reserve(bytes.len())?;
+        self.as_mut_slice()[offset..offset + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Consumes the buffer and returns the raw pointer and capacity, transferring ownership of the
+    /// `vmalloc` allocation to the caller.
+    ///
+    /// The caller becomes responsible for eventually freeing the allocation with `vfree`.
+    pub fn into_raw(self) -> (NonNull<u8>, usize) {
+        let this = core::mem::ManuallyDrop::new(self);
+        (this.mem, this.capacity)
+    }
+}
+
+impl Drop for VmallocBuffer {
+    fn drop(&mut self) {
+        // SAFETY: `mem` was returned by `vzalloc` and has not been freed.
+        unsafe { bindings::vfree(self.mem.as_ptr() as _) };
+    }
+}
+
+// SAFETY: `VmallocBuffer` owns its allocation exclusively and only exposes it through `&`/`&mut`.
+unsafe impl Send for VmallocBuffer {}